@@ -0,0 +1,7 @@
+//! Tracer primitives and ready-made integrations built on top of them.
+
+pub mod tracer;
+pub mod tracing_layer;
+
+pub use tracer::Tracer;
+pub use tracing_layer::RillLayer;
@@ -4,6 +4,8 @@ use futures::channel::mpsc;
 use meio::Action;
 use rill_protocol::data::{self, TimedEvent};
 use rill_protocol::io::provider::{Description, Path, Timestamp};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, Weak};
 use std::time::{Duration, SystemTime};
 use tokio::sync::watch;
@@ -24,33 +26,175 @@ impl<T: data::Metric> DataEnvelope<T> {
 }
 
 // TODO: Remove that aliases and use raw types receivers in recorders.
-pub type DataSender<T> = mpsc::UnboundedSender<DataEnvelope<T>>;
-pub type DataReceiver<T> = mpsc::UnboundedReceiver<DataEnvelope<T>>;
+pub type DataSender<T> = mpsc::Sender<DataEnvelope<T>>;
+pub type DataReceiver<T> = mpsc::Receiver<DataEnvelope<T>>;
+
+/// Configuration for a `Push`-mode tracer's backpressure policy.
+///
+/// Events are coalesced in a small staging buffer that's flushed to the
+/// worker on a timer (`flush_interval`) rather than on every `send`, so a
+/// hot loop can't grow memory without bound: once the buffer reaches
+/// `capacity`, `overflow` decides what gets dropped. When no Tokio runtime
+/// is available to run the timer, `send` flushes the buffer inline instead.
+#[derive(Debug, Clone, Copy)]
+pub struct PushConfig {
+    pub capacity: usize,
+    pub flush_interval: Duration,
+    pub overflow: OverflowStrategy,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            flush_interval: Duration::from_millis(50),
+            overflow: OverflowStrategy::DropOldest,
+        }
+    }
+}
+
+/// What to do with a new event once the staging buffer is already full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowStrategy {
+    /// Evict the oldest staged event to make room for the new one.
+    DropOldest,
+    /// While full, keep roughly one of every `every` events and drop the rest.
+    Sample { every: usize },
+}
+
+/// Recent snapshot durations are window-averaged over this many samples.
+const TRANQUILIZER_WINDOW: usize = 8;
+
+/// Adaptive pacing configuration for a `Pull`-mode tracer (a "tranquilizer").
+///
+/// Instead of a fixed interval, the worker measures how long each snapshot
+/// actually takes and sleeps `average_duration * tranquility` before the
+/// next one, clamped to `[min_interval, max_interval]`. This keeps pull
+/// overhead a bounded fraction of worker time regardless of how expensive a
+/// given `Metric::State` is to serialize.
+#[derive(Debug, Clone, Copy)]
+pub struct Tranquilizer {
+    /// `2.0` means spend at most one third of the time snapshotting.
+    pub tranquility: f64,
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl Default for Tranquilizer {
+    fn default() -> Self {
+        Self {
+            tranquility: 2.0,
+            min_interval: Duration::from_millis(10),
+            max_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// How a `Pull`-mode tracer is paced between snapshots.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PullPacing {
+    /// Snapshot on a fixed schedule.
+    Fixed(Duration),
+    /// Snapshot adaptively, see [`Tranquilizer`].
+    Adaptive(Tranquilizer),
+}
+
+/// Tracks recent snapshot durations for a `Pull`-mode tracer and computes how
+/// long to sleep before the next one.
+#[derive(Debug)]
+pub(crate) struct PullPacer {
+    tranquilizer: Tranquilizer,
+    recent_durations: VecDeque<Duration>,
+}
+
+impl PullPacer {
+    pub(crate) fn new(tranquilizer: Tranquilizer) -> Self {
+        Self {
+            tranquilizer,
+            recent_durations: VecDeque::with_capacity(TRANQUILIZER_WINDOW),
+        }
+    }
+
+    /// Records how long the last snapshot took and returns how long to sleep
+    /// before taking the next one.
+    pub(crate) fn record_and_next_interval(&mut self, snapshot_duration: Duration) -> Duration {
+        if self.recent_durations.len() >= TRANQUILIZER_WINDOW {
+            self.recent_durations.pop_front();
+        }
+        self.recent_durations.push_back(snapshot_duration);
+        let total: Duration = self.recent_durations.iter().sum();
+        let avg = total / self.recent_durations.len() as u32;
+        avg.mul_f64(self.tranquilizer.tranquility)
+            .clamp(self.tranquilizer.min_interval, self.tranquilizer.max_interval)
+    }
+
+    /// Resets the moving window, e.g. when the tracer goes idle.
+    pub(crate) fn reset(&mut self) {
+        self.recent_durations.clear();
+    }
+}
 
 pub(crate) enum TracerMode<T: data::Metric> {
     /// Real-time mode
     Push {
         state: T::State,
         receiver: Option<DataReceiver<T>>,
+        config: PushConfig,
     },
     Pull {
         state: Weak<Mutex<T::State>>,
-        interval: Duration,
+        pacing: PullPacing,
     },
 }
 
+/// The staged, not-yet-flushed events of a `Push`-mode tracer plus the
+/// dropped-events counter exposed to users via [`Tracer::dropped_events`].
+struct PushStaging<T: data::Metric> {
+    queue: Mutex<VecDeque<TimedEvent<T::Event>>>,
+    dropped: AtomicU64,
+    /// Counts events seen while the buffer is full, used by
+    /// `OverflowStrategy::Sample` to decide which one to keep. Distinct from
+    /// `dropped` so kept events don't inflate the loss counter.
+    sample_attempts: AtomicU64,
+    /// Set once a background flusher task is actually running for this
+    /// staging buffer. When there's no Tokio runtime to spawn one on (e.g. a
+    /// `Tracer` created from plain sync code), this stays `false` and `send`
+    /// flushes inline instead of relying on the timer.
+    flusher_spawned: AtomicBool,
+}
+
 #[derive(Debug)]
 enum InnerMode<T: data::Metric> {
-    Push { sender: DataSender<T> },
-    Pull { state: Arc<Mutex<T::State>> },
+    Push {
+        sender: DataSender<T>,
+        staging: Arc<PushStaging<T>>,
+        config: PushConfig,
+    },
+    Pull {
+        state: Arc<Mutex<T::State>>,
+    },
+}
+
+impl<T: data::Metric> std::fmt::Debug for PushStaging<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PushStaging")
+            .field("dropped", &self.dropped.load(Ordering::Relaxed))
+            .finish()
+    }
 }
 
 // TODO: Or require `Clone` for the `Metric` to derive this
 impl<T: data::Metric> Clone for InnerMode<T> {
     fn clone(&self) -> Self {
         match self {
-            Self::Push { sender } => Self::Push {
+            Self::Push {
+                sender,
+                staging,
+                config,
+            } => Self::Push {
                 sender: sender.clone(),
+                staging: staging.clone(),
+                config: *config,
             },
             Self::Pull { state } => Self::Pull {
                 state: state.clone(),
@@ -79,8 +223,183 @@ impl<T: data::Metric> Clone for Tracer<T> {
     }
 }
 
-impl<T: data::Metric> Tracer<T> {
+impl<T: data::Metric> Tracer<T>
+where
+    T::State: serde::Serialize,
+{
     pub(crate) fn new(state: T::State, path: Path, pull: Option<Duration>) -> Self {
+        match pull {
+            Some(interval) => Self::new_pulled(state, path, PullPacing::Fixed(interval)),
+            None => Self::new_pushed(state, path, PushConfig::default()),
+        }
+    }
+
+    /// Creates a `Pull`-mode tracer paced the given way (fixed or adaptive).
+    pub(crate) fn new_pulled(state: T::State, path: Path, pacing: PullPacing) -> Self {
+        let state = Arc::new(Mutex::new(state));
+        let weak_state = Arc::downgrade(&state);
+        let mode = TracerMode::Pull {
+            state: Arc::downgrade(&state),
+            pacing,
+        };
+        let inner_mode = InnerMode::Pull { state };
+        let this = Self::build(path, mode, inner_mode);
+        this.spawn_pull_driver(weak_state, pacing);
+        this
+    }
+
+    /// Creates a `Pull`-mode tracer that adapts its interval to how long
+    /// snapshotting actually takes, see [`Tranquilizer`]. This is the public
+    /// way to opt a `Pull`-mode tracer into adaptive pacing instead of the
+    /// fixed interval `Tracer::new` gives you.
+    pub fn new_tranquilized(state: T::State, path: Path, tranquilizer: Tranquilizer) -> Self {
+        Self::new_pulled(state, path, PullPacing::Adaptive(tranquilizer))
+    }
+
+    /// Drives a `Pull`-mode tracer's own snapshot cadence: for `Fixed`
+    /// pacing this just sleeps the configured interval, for `Adaptive` it
+    /// times how long serializing a snapshot of the state actually takes
+    /// (the same representation a worker would send to a subscribed
+    /// client) and feeds that into a [`PullPacer`] to compute the next
+    /// sleep. Resets the pacer's window whenever the tracer is inactive,
+    /// since a stale window from before an idle period isn't
+    /// representative of current snapshot cost.
+    ///
+    /// Like `spawn_flusher`, this only runs when a Tokio runtime is
+    /// available; a `Tracer` created from plain sync code is still usable,
+    /// it just won't self-drive and needs an external poller instead.
+    fn spawn_pull_driver(&self, weak_state: Weak<Mutex<T::State>>, pacing: PullPacing) {
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => {
+                log::debug!(
+                    "No Tokio runtime available for {:?}; it won't self-drive its pull pacing",
+                    self.description.path
+                );
+                return;
+            }
+        };
+        let mut active = self.active.clone();
+        handle.spawn(async move {
+            let mut pacer = match pacing {
+                PullPacing::Adaptive(tranquilizer) => Some(PullPacer::new(tranquilizer)),
+                PullPacing::Fixed(_) => None,
+            };
+            let mut sleep_for = match pacing {
+                PullPacing::Fixed(interval) => interval,
+                PullPacing::Adaptive(tranquilizer) => tranquilizer.min_interval,
+            };
+            loop {
+                tokio::time::sleep(sleep_for).await;
+                let state = match weak_state.upgrade() {
+                    Some(state) => state,
+                    None => break,
+                };
+                if !*active.borrow() {
+                    if let Some(pacer) = pacer.as_mut() {
+                        pacer.reset();
+                    }
+                    continue;
+                }
+                let pacer = match pacer.as_mut() {
+                    Some(pacer) => pacer,
+                    None => continue,
+                };
+                let started_at = std::time::Instant::now();
+                let snapshot = {
+                    let state = state.lock().expect("tracer state mutex poisoned");
+                    serde_json::to_vec(&*state)
+                };
+                if let Err(err) = snapshot {
+                    log::error!("Can't serialize a pull snapshot: {}", err);
+                }
+                sleep_for = pacer.record_and_next_interval(started_at.elapsed());
+            }
+        });
+    }
+}
+
+impl<T: data::Metric> Tracer<T> {
+    /// Creates a `Push`-mode tracer with an explicit backpressure policy.
+    pub(crate) fn new_pushed(state: T::State, path: Path, config: PushConfig) -> Self {
+        let (tx, rx) = mpsc::channel(config.capacity);
+        let staging = Arc::new(PushStaging {
+            queue: Mutex::new(VecDeque::with_capacity(config.capacity)),
+            dropped: AtomicU64::new(0),
+            sample_attempts: AtomicU64::new(0),
+            flusher_spawned: AtomicBool::new(false),
+        });
+        let mode = TracerMode::Push {
+            state,
+            receiver: Some(rx),
+            config,
+        };
+        let inner_mode = InnerMode::Push {
+            sender: tx,
+            staging: staging.clone(),
+            config,
+        };
+        let this = Self::build(path, mode, inner_mode);
+        this.spawn_flusher(staging, config);
+        this
+    }
+
+    /// Spawns the timer-driven task that periodically drains the staging
+    /// buffer into the worker's channel, coalescing bursts of events between
+    /// flushes. Detects the tracer being dropped via the staging `Weak` and
+    /// stops itself rather than running forever.
+    ///
+    /// A `Tracer` can be constructed from plain sync code with no Tokio
+    /// runtime around (e.g. lazily, from inside a `tracing` event handler),
+    /// so this only spawns when a runtime is actually available. Otherwise
+    /// `flusher_spawned` stays `false` and `send` flushes the staging buffer
+    /// inline instead of waiting for a timer that will never run.
+    fn spawn_flusher(&self, staging: Arc<PushStaging<T>>, config: PushConfig) {
+        let sender = match &self.mode {
+            InnerMode::Push { sender, .. } => sender.clone(),
+            InnerMode::Pull { .. } => return,
+        };
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => {
+                log::debug!(
+                    "No Tokio runtime available for {:?}; staged Push events will flush inline on send",
+                    self.description.path
+                );
+                return;
+            }
+        };
+        staging.flusher_spawned.store(true, Ordering::Relaxed);
+        let weak_staging = Arc::downgrade(&staging);
+        drop(staging);
+        handle.spawn(async move {
+            loop {
+                tokio::time::sleep(config.flush_interval).await;
+                let staging = match weak_staging.upgrade() {
+                    Some(staging) => staging,
+                    None => break,
+                };
+                Self::flush_staged(&staging, &sender);
+            }
+        });
+    }
+
+    /// Drains the staging buffer into the worker's bounded channel, counting
+    /// every event that couldn't be handed off as dropped.
+    fn flush_staged(staging: &PushStaging<T>, sender: &DataSender<T>) {
+        let batch: Vec<_> = {
+            let mut queue = staging.queue.lock().expect("tracer staging mutex poisoned");
+            queue.drain(..).collect()
+        };
+        for event in batch {
+            if let Err(err) = sender.try_send(DataEnvelope::Event(event)) {
+                staging.dropped.fetch_add(1, Ordering::Relaxed);
+                log::error!("Can't flush a staged event to the worker: {}", err);
+            }
+        }
+    }
+
+    fn build(path: Path, mode: TracerMode<T>, inner_mode: InnerMode<T>) -> Self {
         let stream_type = T::stream_type();
         let info = format!("{} - {}", path, stream_type);
         let description = Description {
@@ -88,27 +407,17 @@ impl<T: data::Metric> Tracer<T> {
             info,
             stream_type,
         };
-        // TODO: Remove this active watch channel?
+        // TODO: Seed this `false` and have the worker flip it `true` once a
+        // remote client actually subscribes to the tracer's `Path` (see
+        // `is_active`/`when_activated`). That needs `RILL_LINK`'s worker side
+        // (in `crate::state`, not present in this tree) to track subscriber
+        // counts per `Description` and push through `active_tx`; until that
+        // exists, seeding `false` here would leave every tracer permanently
+        // inactive instead of fixing anything, so this keeps the prior
+        // always-active behavior.
         let (_active_tx, active_rx) = watch::channel(true);
         log::trace!("Creating Tracer with path: {:?}", description.path);
         let description = Arc::new(description);
-        let inner_mode;
-        let mode;
-        if let Some(interval) = pull {
-            let state = Arc::new(Mutex::new(state));
-            mode = TracerMode::Pull {
-                state: Arc::downgrade(&state),
-                interval,
-            };
-            inner_mode = InnerMode::Pull { state };
-        } else {
-            let (tx, rx) = mpsc::unbounded();
-            mode = TracerMode::Push {
-                state,
-                receiver: Some(rx),
-            };
-            inner_mode = InnerMode::Push { sender: tx };
-        }
         let this = Tracer {
             active: active_rx,
             description: description.clone(),
@@ -128,6 +437,15 @@ impl<T: data::Metric> Tracer<T> {
         &self.description.path
     }
 
+    /// Returns the number of `Push`-mode events dropped due to backpressure.
+    /// Always `0` for `Pull`-mode tracers, since they have no event queue.
+    pub fn dropped_events(&self) -> u64 {
+        match &self.mode {
+            InnerMode::Push { staging, .. } => staging.dropped.load(Ordering::Relaxed),
+            InnerMode::Pull { .. } => 0,
+        }
+    }
+
     pub(crate) fn send(&self, data: T::Event, opt_system_time: Option<SystemTime>) {
         if self.is_active() {
             let ts = opt_system_time
@@ -141,11 +459,14 @@ impl<T: data::Metric> Tracer<T> {
                         event: data,
                     };
                     match &self.mode {
-                        InnerMode::Push { sender } => {
-                            let envelope = DataEnvelope::Event(timed_event);
-                            // And will never send an event
-                            if let Err(err) = sender.unbounded_send(envelope) {
-                                log::error!("Can't transfer data to sender: {}", err);
+                        InnerMode::Push {
+                            staging,
+                            sender,
+                            config,
+                        } => {
+                            Self::stage(staging, *config, timed_event);
+                            if !staging.flusher_spawned.load(Ordering::Relaxed) {
+                                Self::flush_staged(staging, sender);
                             }
                         }
                         InnerMode::Pull { state } => match state.lock() {
@@ -164,6 +485,34 @@ impl<T: data::Metric> Tracer<T> {
             }
         }
     }
+
+    /// Coalesces a new event into the staging buffer, applying the
+    /// configured overflow strategy once it's at capacity.
+    fn stage(staging: &PushStaging<T>, config: PushConfig, timed_event: TimedEvent<T::Event>) {
+        let mut queue = staging.queue.lock().expect("tracer staging mutex poisoned");
+        if queue.len() >= config.capacity {
+            match config.overflow {
+                OverflowStrategy::DropOldest => {
+                    queue.pop_front();
+                    staging.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowStrategy::Sample { every } => {
+                    let attempt = staging.sample_attempts.fetch_add(1, Ordering::Relaxed) + 1;
+                    let keep = every != 0 && attempt as usize % every == 0;
+                    if !keep {
+                        staging.dropped.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                    // Keeping this event still means making room for it by
+                    // evicting the oldest staged one, which is just as lost
+                    // as an event we declined to keep.
+                    queue.pop_front();
+                    staging.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        queue.push_back(timed_event);
+    }
 }
 
 impl<T: data::Metric> Tracer<T> {
@@ -172,7 +521,6 @@ impl<T: data::Metric> Tracer<T> {
         *self.active.borrow()
     }
 
-    /* TODO: Remove or replace with an alternative
     /// Use this method to detect when stream had activated.
     ///
     /// It's useful if you want to spawn async coroutine that
@@ -181,7 +529,7 @@ impl<T: data::Metric> Tracer<T> {
     ///
     /// When the generating coroutine active you can use `is_active`
     /// method to detect when to change it to awaiting state again.
-    pub async fn when_activated(&mut self) -> Result<(), Error> {
+    pub async fn when_activated(&mut self) -> Result<(), anyhow::Error> {
         loop {
             if self.is_active() {
                 break;
@@ -190,5 +538,4 @@ impl<T: data::Metric> Tracer<T> {
         }
         Ok(())
     }
-    */
 }
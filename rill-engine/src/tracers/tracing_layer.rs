@@ -0,0 +1,158 @@
+//! A [`tracing_subscriber::Layer`] that feeds `tracing` spans and events into
+//! rillrate tracers.
+//!
+//! This gives applications that are already instrumented with the `tracing`
+//! crate live rillrate streams for free: every event becomes a line in a
+//! [`LogMetric`] tracer keyed by its target, and every span records its entry
+//! count (via [`CounterMetric`]) and latency (via [`HistogramMetric`]), keyed
+//! by its name. A tracer is created lazily the first time its `Path` is seen
+//! and cached for the rest of the process.
+
+use super::tracer::Tracer;
+use rill_protocol::data::{
+    CounterEvent, CounterMetric, CounterState, HistogramEvent, HistogramMetric, HistogramState,
+    LogEvent, LogMetric, LogState,
+};
+use rill_protocol::io::provider::Path;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Latency bucket bounds (in seconds) used for every span's histogram tracer.
+const DEFAULT_LATENCY_BUCKETS: &[f64] = &[
+    0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0,
+];
+
+/// The moment a span was last entered, stashed in its extensions.
+struct SpanTiming {
+    entered_at: Instant,
+}
+
+/// The pair of tracers kept for one span name.
+struct SpanTracers {
+    latency: Tracer<HistogramMetric>,
+    entries: Tracer<CounterMetric>,
+}
+
+/// Feeds `tracing` spans and events into rillrate tracers.
+#[derive(Default)]
+pub struct RillLayer {
+    messages: Mutex<HashMap<Path, Tracer<LogMetric>>>,
+    spans: Mutex<HashMap<Path, SpanTracers>>,
+}
+
+impl RillLayer {
+    /// Creates a new, empty layer with no tracers created yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn message_tracer(&self, path: &Path) -> Tracer<LogMetric> {
+        let mut messages = self.messages.lock().expect("rill layer cache poisoned");
+        messages
+            .entry(path.clone())
+            .or_insert_with(|| Tracer::new(LogState::default(), path.clone(), None))
+            .clone()
+    }
+
+    fn span_tracers(&self, path: &Path) -> (Tracer<HistogramMetric>, Tracer<CounterMetric>) {
+        let mut spans = self.spans.lock().expect("rill layer cache poisoned");
+        let tracers = spans.entry(path.clone()).or_insert_with(|| SpanTracers {
+            latency: Tracer::new(HistogramState::new(DEFAULT_LATENCY_BUCKETS), path.clone(), None),
+            entries: Tracer::new(CounterState::default(), path.clone(), None),
+        });
+        (tracers.latency.clone(), tracers.entries.clone())
+    }
+}
+
+/// Turns a `tracing` target or span name into a rillrate `Path`.
+fn path_for(raw: &str) -> Option<Path> {
+    let path = raw.replace("::", ".").parse().ok();
+    if path.is_none() {
+        log::debug!("Can't turn tracing name {:?} into a rillrate Path, skipping it", raw);
+    }
+    path
+}
+
+/// Renders an event's fields the way `tracing_subscriber`'s default
+/// formatter would: the `message` field as-is, other fields as `key=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            if !self.message.is_empty() {
+                self.message.push(' ');
+            }
+            let _ = write!(self.message, "{}={:?}", field.name(), value);
+        }
+    }
+}
+
+impl<S> Layer<S> for RillLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let path = match path_for(event.metadata().target()) {
+            Some(path) => path,
+            None => return,
+        };
+        let tracer = self.message_tracer(&path);
+        if !tracer.is_active() {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        tracer.send(LogEvent::Message(visitor.message), None);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let path = match path_for(span.metadata().name()) {
+            Some(path) => path,
+            None => return,
+        };
+        let (_, entries) = self.span_tracers(&path);
+        if entries.is_active() {
+            entries.send(CounterEvent::Inc(1.0), None);
+        }
+        span.extensions_mut().insert(SpanTiming {
+            entered_at: Instant::now(),
+        });
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let timing = span.extensions_mut().remove::<SpanTiming>();
+        let entered_at = match timing {
+            Some(timing) => timing.entered_at,
+            None => return,
+        };
+        let path = match path_for(span.metadata().name()) {
+            Some(path) => path,
+            None => return,
+        };
+        let (latency, _) = self.span_tracers(&path);
+        if latency.is_active() {
+            latency.send(HistogramEvent::Add(entered_at.elapsed().as_secs_f64()), None);
+        }
+    }
+}
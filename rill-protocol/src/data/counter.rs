@@ -0,0 +1,39 @@
+use super::{Metric, TimedEvent};
+use crate::io::provider::StreamType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct CounterMetric;
+
+impl Metric for CounterMetric {
+    type State = CounterState;
+    type Event = CounterEvent;
+
+    fn stream_type() -> StreamType {
+        StreamType::from("rillrate.counter.v0")
+    }
+
+    fn apply(state: &mut Self::State, event: TimedEvent<Self::Event>) {
+        match event.event {
+            CounterEvent::Inc(delta) => {
+                state.value += delta;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CounterState {
+    pub value: f64,
+}
+
+impl Default for CounterState {
+    fn default() -> Self {
+        Self { value: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CounterEvent {
+    Inc(f64),
+}
@@ -80,6 +80,61 @@ impl HistogramState {
             pct: Pct::from_div(stat.sum, total),
         })
     }
+
+    /// Approximates the value at quantile `q` (`0.0..=1.0`) the Prometheus
+    /// way: walks the buckets in ascending order accumulating `count` until
+    /// the cumulative count first reaches `q * total_count`, then linearly
+    /// interpolates between the previous bucket's level and the current
+    /// one using the fraction of the rank that falls inside it.
+    ///
+    /// The `+Inf` bucket is clamped to the highest finite level, and a rank
+    /// that falls in the very first bucket just returns that bucket's own
+    /// level (there's no previous bucket to interpolate from). Returns
+    /// `None` if the histogram has no observations yet.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let total_count: u64 = self.buckets.values().map(|stat| stat.count).sum();
+        if total_count == 0 {
+            return None;
+        }
+        let rank = q * total_count as f64;
+        let mut cumulative = 0u64;
+        let mut prev_level: Option<f64> = None;
+        let mut highest_finite = f64::NEG_INFINITY;
+        for (level, stat) in &self.buckets {
+            let level = level.into_inner();
+            cumulative += stat.count;
+            if level.is_finite() {
+                highest_finite = level;
+            }
+            if (cumulative as f64) >= rank {
+                let upper = if level.is_finite() { level } else { highest_finite };
+                return Some(match prev_level {
+                    Some(prev) if stat.count > 0 => {
+                        let fraction = (rank - (cumulative - stat.count) as f64) / stat.count as f64;
+                        prev + (upper - prev) * fraction
+                    }
+                    _ => upper,
+                });
+            }
+            prev_level = Some(if level.is_finite() { level } else { highest_finite });
+        }
+        Some(highest_finite)
+    }
+
+    /// The 50th percentile (median). See [`HistogramState::quantile`].
+    pub fn p50(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    /// The 90th percentile. See [`HistogramState::quantile`].
+    pub fn p90(&self) -> Option<f64> {
+        self.quantile(0.9)
+    }
+
+    /// The 99th percentile. See [`HistogramState::quantile`].
+    pub fn p99(&self) -> Option<f64> {
+        self.quantile(0.99)
+    }
 }
 
 pub struct Bar {
@@ -0,0 +1,51 @@
+use super::{Metric, TimedEvent};
+use crate::io::provider::StreamType;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+static FRAME_SIZE: usize = 20;
+
+#[derive(Debug)]
+pub struct LogMetric;
+
+impl Metric for LogMetric {
+    type State = LogState;
+    type Event = LogEvent;
+
+    fn stream_type() -> StreamType {
+        StreamType::from("rillrate.log.v0")
+    }
+
+    fn apply(state: &mut Self::State, event: TimedEvent<Self::Event>) {
+        let TimedEvent { timestamp, event } = event;
+        match event {
+            LogEvent::Message(message) => {
+                if state.records.len() >= FRAME_SIZE {
+                    state.records.pop_front();
+                }
+                state.records.push_back(TimedEvent {
+                    timestamp,
+                    event: message,
+                });
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogState {
+    pub records: VecDeque<TimedEvent<String>>,
+}
+
+impl Default for LogState {
+    fn default() -> Self {
+        Self {
+            records: VecDeque::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LogEvent {
+    Message(String),
+}
@@ -1,37 +1,149 @@
 use super::tracer::{Tracer, TracerEvent};
 use derive_more::{Deref, DerefMut};
 use rill_protocol::provider::{Description, Path, RillData, RillEvent, StreamType, Timestamp};
-use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::time::SystemTime;
 
 static FRAME_SIZE: usize = 20;
 
+/// A single structured field attached to a templated log record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(value) => write!(f, "{}", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum LogRecord {
-    // TODO: Track hash templates here
     Message(String),
+    /// A templated message with structured fields. Repeated records that
+    /// share the same `template` collapse into a single displayed entry
+    /// with a repeat count instead of filling the ring buffer with
+    /// near-identical strings.
+    Structured {
+        template: &'static str,
+        fields: Vec<(String, Value)>,
+    },
+}
+
+/// Where to find a template's most recent record, so a later repeat of that
+/// same template can collapse into it even if other templates' records were
+/// interleaved in between (a single `last_template` can only catch repeats
+/// that are immediately consecutive).
+#[derive(Debug)]
+struct TemplateSlot {
+    /// Kept alongside the hash as a tie-break, since a `DefaultHasher`
+    /// collision between two distinct templates would otherwise collapse
+    /// them into the same record.
+    template: &'static str,
+    /// Absolute, never-reused position of the record in `LogState::records`,
+    /// see `LogState::base_position`.
+    position: u64,
+    repeats: u64,
 }
 
 #[derive(Debug, Default)]
 pub struct LogState {
     records: VecDeque<RillEvent>,
+    /// Absolute position of `records[0]`, the oldest entry still buffered.
+    /// Bumped every time `FRAME_SIZE` eviction pops the front, so a
+    /// `TemplateSlot::position` recorded before an eviction can still be
+    /// resolved (or correctly found stale) afterwards.
+    base_position: u64,
+    /// The most recent record for each template seen, keyed by a hash of
+    /// the template string.
+    templates: HashMap<u64, TemplateSlot>,
+}
+
+impl LogState {
+    /// Appends `event`, evicting the oldest record once over `FRAME_SIZE`,
+    /// and returns the index `event` was stored at.
+    fn push(&mut self, event: RillEvent) -> usize {
+        if self.records.len() > FRAME_SIZE {
+            self.records.pop_front();
+            self.base_position += 1;
+        }
+        self.records.push_back(event);
+        self.records.len() - 1
+    }
+
+    /// Resolves an absolute position to a current index in `records`, or
+    /// `None` if that record has since been evicted.
+    fn local_index(&self, position: u64) -> Option<usize> {
+        position
+            .checked_sub(self.base_position)
+            .map(|offset| offset as usize)
+            .filter(|&index| index < self.records.len())
+    }
 }
 
 impl TracerEvent for LogRecord {
     type State = LogState;
 
     fn aggregate(self, state: &mut Self::State, timestamp: Timestamp) -> Option<&RillEvent> {
-        match self {
+        let index = match self {
             Self::Message(msg) => {
-                if state.records.len() > FRAME_SIZE {
-                    state.records.pop_front();
-                }
                 let data = RillData::LogRecord { message: msg };
-                let last_event = RillEvent { timestamp, data };
-                state.records.push_back(last_event);
-                state.records.back()
+                state.push(RillEvent { timestamp, data })
             }
-        }
+            Self::Structured { template, fields } => {
+                let hash = hash_template(template);
+                let existing = state.templates.get(&hash).and_then(|slot| {
+                    if slot.template == template {
+                        state.local_index(slot.position)
+                    } else {
+                        None
+                    }
+                });
+                match existing {
+                    Some(index) => {
+                        // Collapse this repeat into its own previous record
+                        // instead of growing the buffer with the same
+                        // template again, wherever that record currently is.
+                        let slot = state.templates.get_mut(&hash).expect("checked above");
+                        slot.repeats += 1;
+                        let message = render_template(template, &fields, slot.repeats);
+                        state.records[index] = RillEvent {
+                            timestamp,
+                            data: RillData::LogRecord { message },
+                        };
+                        index
+                    }
+                    None => {
+                        let message = render_template(template, &fields, 1);
+                        let data = RillData::LogRecord { message };
+                        let index = state.push(RillEvent { timestamp, data });
+                        let position = state.base_position + index as u64;
+                        state.templates.insert(
+                            hash,
+                            TemplateSlot {
+                                template,
+                                position,
+                                repeats: 1,
+                            },
+                        );
+                        index
+                    }
+                }
+            }
+        };
+        state.records.get(index)
     }
 
     fn to_snapshot(state: &Self::State) -> Vec<RillEvent> {
@@ -39,6 +151,31 @@ impl TracerEvent for LogRecord {
     }
 }
 
+/// Hashes a template string so repeated records can be deduplicated without
+/// storing the template itself more than once per run.
+fn hash_template(template: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    template.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Renders a template with its fields, appending a `(xN)` repeat count once
+/// the same template has fired more than once while its record is still
+/// buffered, whether or not other records came in between.
+fn render_template(template: &str, fields: &[(String, Value)], count: u64) -> String {
+    let mut message = template.to_string();
+    if !fields.is_empty() {
+        let rendered: Vec<String> = fields.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        message.push_str(" [");
+        message.push_str(&rendered.join(", "));
+        message.push(']');
+    }
+    if count > 1 {
+        message.push_str(&format!(" (x{})", count));
+    }
+    message
+}
+
 /// This tracer sends text messages.
 #[derive(Debug, Deref, DerefMut)]
 pub struct LogTracer {
@@ -63,4 +200,19 @@ impl LogTracer {
         let data = LogRecord::Message(message);
         self.tracer.send(data, timestamp);
     }
+
+    /// Writes a templated message with structured fields.
+    ///
+    /// Repeated calls with the same `template` collapse into a single
+    /// record with a `(xN)` repeat count, so a burst of identical log
+    /// lines doesn't fill the fixed-size buffer with duplicates.
+    pub fn log_structured(
+        &self,
+        template: &'static str,
+        fields: Vec<(String, Value)>,
+        timestamp: Option<SystemTime>,
+    ) {
+        let data = LogRecord::Structured { template, fields };
+        self.tracer.send(data, timestamp);
+    }
 }